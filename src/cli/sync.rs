@@ -1,17 +1,20 @@
 use std::{
+    collections::HashMap,
     io::{BufReader, BufWriter, Write as _},
     path::{Path, PathBuf},
 };
 
 use anyhow::{bail, Context as _};
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use fs_err::File;
 use memofs::Vfs;
-use rbx_dom_weak::{InstanceBuilder, WeakDom};
+use rbx_dom_weak::{types::Ref, InstanceBuilder, WeakDom};
 
 use crate::{
     project::Project,
-    snapshot::{apply_patch_set, compute_patch_set, InstanceContext, InstanceSnapshot, RojoTree},
+    snapshot::{
+        apply_patch_set, compute_patch_set, InstanceContext, InstanceSnapshot, PatchSet, RojoTree,
+    },
     snapshot_middleware::snapshot_from_vfs,
 };
 
@@ -40,6 +43,76 @@ pub struct SyncCommand {
     /// Where to output the merged result.
     #[clap(long, short)]
     pub output: PathBuf,
+
+    /// How to reconcile differences between the project and the input file.
+    ///
+    /// `preserve` (the default) never deletes instances or unsets properties
+    /// that only exist in the input file. `overwrite` and `project-wins` let
+    /// the project snapshot win outright, including removals and property
+    /// unsets. `file-wins` keeps whatever the input file already defines
+    /// whenever the project would otherwise change it. When `--base` is
+    /// given, property changes that only the project made are applied under
+    /// every strategy, and this decides how real conflicts (both sides
+    /// changed the same property to different values) are resolved;
+    /// `preserve` and `file-wins` still never delete instances.
+    #[clap(long, value_enum, default_value = "preserve")]
+    pub strategy: MergeStrategy,
+
+    /// Path to a common-ancestor file, enabling a three-way merge.
+    ///
+    /// When set, `sync` diffs both the project and the input file against
+    /// this ancestor instead of treating the input file as a passive target.
+    /// Changes that only one side made are applied; changes both sides made
+    /// identically are no-ops; changes the two sides disagree on are
+    /// conflicts, resolved according to `--strategy`.
+    #[clap(long)]
+    pub base: Option<PathBuf>,
+
+    /// Log unresolved three-way merge conflicts (`--strategy preserve`, the
+    /// default, never resolves a real conflict) and keep the file's current
+    /// value instead of failing the run. Off by default: there is no
+    /// interactive way to resolve a conflict, so a genuine one fails `sync`
+    /// unless this is set.
+    #[clap(long)]
+    pub allow_conflicts: bool,
+
+    /// Compute and print the patch that would be applied instead of writing
+    /// the output file.
+    #[clap(long)]
+    pub dry_run: bool,
+
+    /// Alias for `--dry-run`, matching `rojo syncback`'s flag of the same
+    /// name.
+    #[clap(long)]
+    pub list: bool,
+}
+
+/// Controls how [`SyncCommand`] reconciles the project snapshot against the
+/// input file when they disagree about an instance or property.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[clap(rename_all = "kebab-case")]
+pub enum MergeStrategy {
+    /// Never delete the input file's instances; without `--base`, also never
+    /// unset its properties. Additions and property value changes apply.
+    Preserve,
+    /// Let the project snapshot win, including removals and property unsets.
+    Overwrite,
+    /// Alias for `overwrite`.
+    ProjectWins,
+    /// Keep the input file's value whenever the project would change it
+    /// (with `--base`, only when both sides changed it).
+    FileWins,
+}
+
+impl std::fmt::Display for MergeStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            MergeStrategy::Preserve => "preserve",
+            MergeStrategy::Overwrite => "overwrite",
+            MergeStrategy::ProjectWins => "project-wins",
+            MergeStrategy::FileWins => "file-wins",
+        })
+    }
 }
 
 impl SyncCommand {
@@ -58,31 +131,323 @@ impl SyncCommand {
         let desired_snapshot = snapshot_from_vfs(&instance_context, &vfs, project_path.as_ref())?;
 
         // Load the input file into a RojoTree.
-        let dom_old = read_dom(&self.input, input_kind)?;
+        let (dom_old, old_is_single_root) = read_dom(&self.input, input_kind)?;
         let old_root = dom_old.root_ref();
         let mut tree_old = RojoTree::new(InstanceSnapshot::from_tree(dom_old, old_root));
 
-        let root_id = tree_old.get_root_id();
-        let mut patch_set = compute_patch_set(desired_snapshot, &tree_old, root_id);
+        let mut patch_set = if let Some(base_path) = &self.base {
+            let base_kind = FileKind::from_path(base_path).context(UNKNOWN_INPUT_KIND_ERR)?;
+            let (dom_base, _) = read_dom(base_path, base_kind)?;
+            let base_root = dom_base.root_ref();
+            let tree_base = RojoTree::new(InstanceSnapshot::from_tree(dom_base, base_root));
+
+            let project_patch =
+                compute_patch_set(desired_snapshot, &tree_base, tree_base.get_root_id());
+
+            reconcile_three_way(
+                project_patch,
+                &tree_base,
+                &tree_old,
+                self.strategy,
+                self.allow_conflicts,
+            )?
+        } else {
+            let root_id = tree_old.get_root_id();
+            compute_patch_set(desired_snapshot, &tree_old, root_id)
+        };
+
+        apply_merge_strategy(
+            self.strategy,
+            &mut patch_set,
+            &tree_old,
+            self.base.is_some(),
+        );
 
-        // Preserve existing content:
-        // - Do not delete any instances that exist in the input file
-        // - Do not remove any properties that exist in the input file
-        patch_set.removed_instances.clear();
-        for update in &mut patch_set.updated_instances {
-            update
-                .changed_properties
-                .retain(|_, value| value.is_some());
+        if self.dry_run || self.list {
+            print_patch_summary(&patch_set, &tree_old);
+            return Ok(());
         }
 
         apply_patch_set(&mut tree_old, patch_set);
 
-        write_tree_to_file(&tree_old, &self.output, output_kind)?;
+        write_tree_to_file(&tree_old, &self.output, output_kind, old_is_single_root)?;
 
         Ok(())
     }
 }
 
+/// Filters `patch_set` in place so that it only contains the changes allowed
+/// by `strategy`, given the instances and properties already present in
+/// `tree_old`. If `already_reconciled` is true, `patch_set` already went
+/// through [`reconcile_three_way`]'s base-aware filtering, so only the
+/// instance-removal filtering (which that function defers here) still applies.
+fn apply_merge_strategy(
+    strategy: MergeStrategy,
+    patch_set: &mut PatchSet,
+    tree_old: &RojoTree,
+    already_reconciled: bool,
+) {
+    match strategy {
+        MergeStrategy::Preserve => {
+            // Do not delete any instances that exist in the input file, and
+            // do not remove any properties that exist in the input file.
+            patch_set.removed_instances.clear();
+            if !already_reconciled {
+                for update in &mut patch_set.updated_instances {
+                    update.changed_properties.retain(|_, value| value.is_some());
+                }
+            }
+        }
+        MergeStrategy::Overwrite | MergeStrategy::ProjectWins => {
+            // Apply the project snapshot verbatim: removals and property
+            // unsets take effect.
+        }
+        MergeStrategy::FileWins => {
+            // Never delete instances, and drop any property change where the
+            // input file already defines a value for that property.
+            patch_set.removed_instances.clear();
+            if !already_reconciled {
+                for update in &mut patch_set.updated_instances {
+                    let existing = tree_old.get_instance(update.id);
+                    update.changed_properties.retain(|name, _| {
+                        existing
+                            .map(|instance| !instance.properties().contains_key(name))
+                            .unwrap_or(true)
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Reconciles `project_patch` (computed as `base -> desired project`) against
+/// the independent changes `tree_old` has already made relative to
+/// `tree_base`, producing a [`PatchSet`] expressed in terms of `tree_old`'s
+/// instance ids. Instances are correlated between the two trees by
+/// structural path, since they're separate `WeakDom`s with unrelated `Ref`s.
+fn reconcile_three_way(
+    project_patch: PatchSet,
+    tree_base: &RojoTree,
+    tree_old: &RojoTree,
+    strategy: MergeStrategy,
+    allow_conflicts: bool,
+) -> anyhow::Result<PatchSet> {
+    let base_paths = build_id_paths(tree_base.inner(), tree_base.get_root_id());
+    let old_paths = build_id_paths(tree_old.inner(), tree_old.get_root_id());
+    let old_ids_by_path: HashMap<&str, Ref> = old_paths
+        .iter()
+        .map(|(id, path)| (path.as_str(), *id))
+        .collect();
+
+    let translate = |id: Ref| -> Option<Ref> {
+        base_paths
+            .get(&id)
+            .and_then(|path| old_ids_by_path.get(path.as_str()))
+            .copied()
+    };
+
+    let mut unresolved_conflicts = Vec::new();
+    let mut resolved = PatchSet::default();
+
+    for mut add in project_patch.added_instances {
+        match translate(add.parent_id) {
+            Some(old_parent_id) => {
+                add.parent_id = old_parent_id;
+                resolved.added_instances.push(add);
+            }
+            None => {
+                // The parent the project wants to add this instance under no
+                // longer exists in the file since `base` (removed or
+                // renamed). There's no sound id to parent it to in
+                // `tree_old`, so drop the add instead of handing
+                // `apply_patch_set` a `Ref` from a different `WeakDom`.
+                let parent_path = base_paths.get(&add.parent_id).cloned().unwrap_or_default();
+                log::error!(
+                    "Merge conflict at '{}': project wants to add {:?} here, \
+                     but the file no longer has this parent instance",
+                    parent_path,
+                    add.instance.name
+                );
+                // Only `preserve` treats this as unresolved; every other
+                // strategy drops the add outright, `overwrite`/`project-wins`
+                // included, since there is no parent to add it under.
+                if strategy == MergeStrategy::Preserve {
+                    unresolved_conflicts
+                        .push((parent_path, format!("add {:?}", add.instance.name)));
+                }
+            }
+        }
+    }
+
+    for base_id in project_patch.removed_instances {
+        // If the file no longer has this instance either, there's nothing
+        // left to remove. Otherwise, defer to `apply_merge_strategy`.
+        if let Some(old_id) = translate(base_id) {
+            resolved.removed_instances.push(old_id);
+        }
+    }
+
+    for mut update in project_patch.updated_instances {
+        let Some(old_id) = translate(update.id) else {
+            // The file already dropped this instance; nothing to reconcile.
+            continue;
+        };
+        let base_instance = tree_base.inner().get_by_ref(update.id);
+        let old_instance = tree_old.inner().get_by_ref(old_id);
+
+        update.changed_properties.retain(|name, project_value| {
+            let base_value = base_instance.and_then(|i| i.properties.get(name));
+            let file_value = old_instance.and_then(|i| i.properties.get(name));
+
+            if file_value == base_value {
+                // Only the project touched this property.
+                return true;
+            }
+            if project_value.as_ref() == file_value {
+                // Both sides ended up at the same value; nothing to do.
+                return false;
+            }
+
+            let path = old_paths.get(&old_id).cloned().unwrap_or_default();
+            log::error!(
+                "Merge conflict at '{}', property {:?}: project wants {:?}, file has {:?}",
+                path,
+                name,
+                project_value,
+                file_value
+            );
+
+            match strategy {
+                MergeStrategy::Overwrite | MergeStrategy::ProjectWins => true,
+                MergeStrategy::FileWins => false,
+                MergeStrategy::Preserve => {
+                    unresolved_conflicts.push((path, format!("{:?}", name)));
+                    false
+                }
+            }
+        });
+
+        update.id = old_id;
+        resolved.updated_instances.push(update);
+    }
+
+    if !unresolved_conflicts.is_empty() && !allow_conflicts {
+        let summary = unresolved_conflicts
+            .into_iter()
+            .map(|(path, property)| format!("{} ({})", path, property))
+            .collect::<Vec<_>>()
+            .join(", ");
+        bail!(
+            "Refusing to merge: unresolved conflicts with --strategy={}: {}. \
+             Pass --strategy overwrite/project-wins/file-wins or --allow-conflicts.",
+            strategy,
+            summary
+        );
+    }
+
+    Ok(resolved)
+}
+
+/// Prints a human-readable summary of `patch_set`, grouped by instance path,
+/// for `--dry-run`/`--list`. Mirrors the summary `rojo syncback --dry-run`
+/// prints, but for the `sync` direction.
+fn print_patch_summary(patch_set: &PatchSet, tree_old: &RojoTree) {
+    print!("{}", format_patch_summary(patch_set, tree_old));
+}
+
+/// Builds the summary text printed by [`print_patch_summary`]. Split out so
+/// it can be asserted on directly instead of capturing stdout.
+fn format_patch_summary(patch_set: &PatchSet, tree_old: &RojoTree) -> String {
+    use std::fmt::Write as _;
+
+    let old_paths = build_id_paths(tree_old.inner(), tree_old.get_root_id());
+    let mut summary = String::new();
+
+    for add in &patch_set.added_instances {
+        let parent_path = old_paths.get(&add.parent_id).cloned().unwrap_or_default();
+        let path = if parent_path.is_empty() {
+            add.instance.name.to_string()
+        } else {
+            format!("{}/{}", parent_path, add.instance.name)
+        };
+        writeln!(summary, "+ {} ({})", path, add.instance.class_name).unwrap();
+    }
+
+    for id in &patch_set.removed_instances {
+        let path = old_paths.get(id).cloned().unwrap_or_default();
+        writeln!(summary, "- {}", path).unwrap();
+    }
+
+    for update in &patch_set.updated_instances {
+        if update.changed_properties.is_empty() {
+            continue;
+        }
+
+        let path = old_paths.get(&update.id).cloned().unwrap_or_default();
+        writeln!(summary, "~ {}", path).unwrap();
+
+        let existing = tree_old.get_instance(update.id);
+        let mut changed_properties: Vec<_> = update.changed_properties.iter().collect();
+        changed_properties.sort_by_key(|(name, _)| format!("{:?}", name));
+        for (name, new_value) in changed_properties {
+            let old_value = existing.and_then(|instance| instance.properties().get(name));
+            writeln!(
+                summary,
+                "    {:?}: {:?} -> {:?}",
+                name, old_value, new_value
+            )
+            .unwrap();
+        }
+    }
+
+    if summary.is_empty() {
+        summary.push_str("No changes.\n");
+    }
+
+    summary
+}
+
+/// Builds a map from instance id to its structural path (names joined by
+/// `/`, root is the empty string) within `dom`, rooted at `root_id`.
+/// Duplicate sibling names are disambiguated with a `#<n>` suffix for the
+/// 2nd, 3rd, ... instance sharing a name under the same parent.
+fn build_id_paths(dom: &WeakDom, root_id: Ref) -> HashMap<Ref, String> {
+    let mut paths = HashMap::new();
+    let mut stack = vec![(root_id, String::new())];
+
+    while let Some((id, path)) = stack.pop() {
+        let Some(instance) = dom.get_by_ref(id) else {
+            continue;
+        };
+
+        let mut name_occurrences: HashMap<&str, u32> = HashMap::new();
+        for &child_id in instance.children() {
+            let Some(child) = dom.get_by_ref(child_id) else {
+                continue;
+            };
+
+            let occurrence = name_occurrences.entry(child.name.as_str()).or_insert(0);
+            let child_name = if *occurrence == 0 {
+                child.name.clone()
+            } else {
+                format!("{}#{}", child.name, occurrence)
+            };
+            *occurrence += 1;
+
+            let child_path = if path.is_empty() {
+                child_name
+            } else {
+                format!("{}/{}", path, child_name)
+            };
+            stack.push((child_id, child_path));
+        }
+
+        paths.insert(id, path);
+    }
+
+    paths
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum FileKind {
     Rbxmx,
@@ -113,17 +478,22 @@ fn xml_encode_config() -> rbx_xml::EncodeOptions<'static> {
     rbx_xml::EncodeOptions::new().property_behavior(rbx_xml::EncodePropertyBehavior::WriteUnknown)
 }
 
-fn read_dom(path: &Path, file_kind: FileKind) -> anyhow::Result<WeakDom> {
+/// Reads `path` into a `WeakDom`, along with whether its root represents a
+/// single flattened top-level instance (only possible for `.rbxm`/`.rbxmx`).
+fn read_dom(path: &Path, file_kind: FileKind) -> anyhow::Result<(WeakDom, bool)> {
     let content = BufReader::new(File::open(path)?);
     match file_kind {
-        FileKind::Rbxl => rbx_binary::from_reader(content).with_context(|| {
-            format!(
-                "Could not deserialize binary place file at {}",
-                path.display()
-            )
-        }),
+        FileKind::Rbxl => rbx_binary::from_reader(content)
+            .with_context(|| {
+                format!(
+                    "Could not deserialize binary place file at {}",
+                    path.display()
+                )
+            })
+            .map(|dom| (dom, false)),
         FileKind::Rbxlx => rbx_xml::from_reader(content, xml_decode_config())
-            .with_context(|| format!("Could not deserialize XML place file at {}", path.display())),
+            .with_context(|| format!("Could not deserialize XML place file at {}", path.display()))
+            .map(|dom| (dom, false)),
         FileKind::Rbxm => {
             let temp_tree = rbx_binary::from_reader(content).with_context(|| {
                 format!(
@@ -135,15 +505,22 @@ fn read_dom(path: &Path, file_kind: FileKind) -> anyhow::Result<WeakDom> {
             process_model_dom(temp_tree)
         }
         FileKind::Rbxmx => {
-            let temp_tree = rbx_xml::from_reader(content, xml_decode_config()).with_context(|| {
-                format!("Could not deserialize XML model file at {}", path.display())
-            })?;
+            let temp_tree =
+                rbx_xml::from_reader(content, xml_decode_config()).with_context(|| {
+                    format!("Could not deserialize XML model file at {}", path.display())
+                })?;
             process_model_dom(temp_tree)
         }
     }
 }
 
-fn process_model_dom(dom: WeakDom) -> anyhow::Result<WeakDom> {
+/// Normalizes a deserialized `.rbxm`/`.rbxmx` model into a `WeakDom` ready to
+/// feed into the sync pipeline, alongside whether it was flattened to a
+/// single root. When the file has exactly one top-level instance, that
+/// instance becomes the tree's root directly, matching Rojo's historical
+/// model-file semantics; otherwise the deserializer's synthetic container is
+/// kept as-is.
+fn process_model_dom(dom: WeakDom) -> anyhow::Result<(WeakDom, bool)> {
     let temp_children = dom.root().children();
     if temp_children.len() == 1 {
         let real_root = dom.get_by_ref(temp_children[0]).unwrap();
@@ -159,24 +536,30 @@ fn process_model_dom(dom: WeakDom) -> anyhow::Result<WeakDom> {
         for child in children {
             new_tree.transfer_within(child, new_tree.root_ref());
         }
-        Ok(new_tree)
+        Ok((new_tree, true))
     } else {
-        bail!(
-            "Rojo does not currently support models with more than one Instance at the Root!"
-        );
+        Ok((dom, false))
     }
 }
 
-fn write_tree_to_file(tree: &RojoTree, output: &Path, kind: FileKind) -> anyhow::Result<()> {
+fn write_tree_to_file(
+    tree: &RojoTree,
+    output: &Path,
+    kind: FileKind,
+    is_single_root: bool,
+) -> anyhow::Result<()> {
     let mut file = BufWriter::new(File::create(output)?);
 
     let root_id = tree.get_root_id();
 
     match kind {
-        FileKind::Rbxm => {
+        FileKind::Rbxm if is_single_root => {
             rbx_binary::to_writer(&mut file, tree.inner(), &[root_id])?;
         }
-        FileKind::Rbxl => {
+        FileKind::Rbxmx if is_single_root => {
+            rbx_xml::to_writer(&mut file, tree.inner(), &[root_id], xml_encode_config())?;
+        }
+        FileKind::Rbxm | FileKind::Rbxl => {
             let root_instance = tree
                 .inner()
                 .get_by_ref(root_id)
@@ -185,10 +568,7 @@ fn write_tree_to_file(tree: &RojoTree, output: &Path, kind: FileKind) -> anyhow:
 
             rbx_binary::to_writer(&mut file, tree.inner(), top_level_ids)?;
         }
-        FileKind::Rbxmx => {
-            rbx_xml::to_writer(&mut file, tree.inner(), &[root_id], xml_encode_config())?;
-        }
-        FileKind::Rbxlx => {
+        FileKind::Rbxmx | FileKind::Rbxlx => {
             let root_instance = tree
                 .inner()
                 .get_by_ref(root_id)
@@ -203,4 +583,425 @@ fn write_tree_to_file(tree: &RojoTree, output: &Path, kind: FileKind) -> anyhow:
     Ok(())
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rbx_dom_weak::types::Variant;
+
+    fn child(
+        dom: &mut WeakDom,
+        parent: Ref,
+        name: &str,
+        class: &str,
+        properties: &[(&str, Variant)],
+    ) -> Ref {
+        let mut builder = InstanceBuilder::new(class).with_name(name);
+        for (prop_name, value) in properties {
+            builder = builder.with_property(*prop_name, value.clone());
+        }
+        dom.insert(parent, builder)
+    }
+
+    fn tree_from(dom: WeakDom) -> RojoTree {
+        let root = dom.root_ref();
+        RojoTree::new(InstanceSnapshot::from_tree(dom, root))
+    }
+
+    #[test]
+    fn preserve_keeps_property_updates_but_drops_removals() {
+        let mut dom_old = WeakDom::new(InstanceBuilder::new("DataModel"));
+        let old_root = dom_old.root_ref();
+        let part = child(
+            &mut dom_old,
+            old_root,
+            "Part",
+            "Part",
+            &[("Foo", Variant::Int32(1))],
+        );
+        let leftover = child(&mut dom_old, old_root, "Leftover", "Part", &[]);
+        let tree_old = tree_from(dom_old);
+
+        let mut dom_desired = WeakDom::new(InstanceBuilder::new("DataModel"));
+        let desired_root = dom_desired.root_ref();
+        child(
+            &mut dom_desired,
+            desired_root,
+            "Part",
+            "Part",
+            &[("Foo", Variant::Int32(2))],
+        );
+        let desired_snapshot = InstanceSnapshot::from_tree(dom_desired, desired_root);
+
+        let mut patch_set = compute_patch_set(desired_snapshot, &tree_old, tree_old.get_root_id());
+        assert!(patch_set.removed_instances.contains(&leftover));
 
+        apply_merge_strategy(MergeStrategy::Preserve, &mut patch_set, &tree_old, false);
+
+        assert!(patch_set.removed_instances.is_empty());
+        let update = patch_set
+            .updated_instances
+            .iter()
+            .find(|update| update.id == part)
+            .expect("property change should survive preserve");
+        assert_eq!(update.changed_properties.len(), 1);
+    }
+
+    #[test]
+    fn overwrite_leaves_the_patch_set_untouched() {
+        let mut dom_old = WeakDom::new(InstanceBuilder::new("DataModel"));
+        let old_root = dom_old.root_ref();
+        let part = child(
+            &mut dom_old,
+            old_root,
+            "Part",
+            "Part",
+            &[("Foo", Variant::Int32(1))],
+        );
+        let leftover = child(&mut dom_old, old_root, "Leftover", "Part", &[]);
+        let tree_old = tree_from(dom_old);
+
+        let mut dom_desired = WeakDom::new(InstanceBuilder::new("DataModel"));
+        let desired_root = dom_desired.root_ref();
+        child(
+            &mut dom_desired,
+            desired_root,
+            "Part",
+            "Part",
+            &[("Foo", Variant::Int32(2))],
+        );
+        let desired_snapshot = InstanceSnapshot::from_tree(dom_desired, desired_root);
+
+        let mut patch_set = compute_patch_set(desired_snapshot, &tree_old, tree_old.get_root_id());
+
+        apply_merge_strategy(MergeStrategy::Overwrite, &mut patch_set, &tree_old, false);
+
+        assert!(patch_set.removed_instances.contains(&leftover));
+        let update = patch_set
+            .updated_instances
+            .iter()
+            .find(|update| update.id == part)
+            .expect("overwrite should not touch property changes");
+        assert_eq!(update.changed_properties.len(), 1);
+    }
+
+    #[test]
+    fn file_wins_drops_properties_the_file_already_defines() {
+        let mut dom_old = WeakDom::new(InstanceBuilder::new("DataModel"));
+        let old_root = dom_old.root_ref();
+        let part = child(
+            &mut dom_old,
+            old_root,
+            "Part",
+            "Part",
+            &[("Foo", Variant::Int32(1))],
+        );
+        child(&mut dom_old, old_root, "Leftover", "Part", &[]);
+        let tree_old = tree_from(dom_old);
+
+        let mut dom_desired = WeakDom::new(InstanceBuilder::new("DataModel"));
+        let desired_root = dom_desired.root_ref();
+        child(
+            &mut dom_desired,
+            desired_root,
+            "Part",
+            "Part",
+            &[("Foo", Variant::Int32(2))],
+        );
+        let desired_snapshot = InstanceSnapshot::from_tree(dom_desired, desired_root);
+
+        let mut patch_set = compute_patch_set(desired_snapshot, &tree_old, tree_old.get_root_id());
+
+        apply_merge_strategy(MergeStrategy::FileWins, &mut patch_set, &tree_old, false);
+
+        assert!(patch_set.removed_instances.is_empty());
+        let update = patch_set
+            .updated_instances
+            .iter()
+            .find(|update| update.id == part)
+            .expect("Part should still have an update entry");
+        assert!(update.changed_properties.is_empty());
+    }
+
+    #[test]
+    fn build_id_paths_disambiguates_duplicate_sibling_names() {
+        let mut dom = WeakDom::new(InstanceBuilder::new("DataModel"));
+        let root = dom.root_ref();
+        let first = child(&mut dom, root, "Part", "Part", &[]);
+        let second = child(&mut dom, root, "Part", "Part", &[]);
+
+        let paths = build_id_paths(&dom, root);
+
+        assert_eq!(paths[&first], "Part");
+        assert_eq!(paths[&second], "Part#1");
+    }
+
+    #[test]
+    fn reconcile_three_way_applies_one_sided_project_changes() {
+        let mut dom_base = WeakDom::new(InstanceBuilder::new("DataModel"));
+        let base_root = dom_base.root_ref();
+        let part = child(
+            &mut dom_base,
+            base_root,
+            "Part",
+            "Part",
+            &[("Foo", Variant::Int32(1))],
+        );
+        let tree_base = tree_from(dom_base);
+
+        let mut dom_old = WeakDom::new(InstanceBuilder::new("DataModel"));
+        let old_root = dom_old.root_ref();
+        child(
+            &mut dom_old,
+            old_root,
+            "Part",
+            "Part",
+            &[("Foo", Variant::Int32(1))],
+        );
+        let tree_old = tree_from(dom_old);
+
+        let mut dom_desired = WeakDom::new(InstanceBuilder::new("DataModel"));
+        let desired_root = dom_desired.root_ref();
+        child(
+            &mut dom_desired,
+            desired_root,
+            "Part",
+            "Part",
+            &[("Foo", Variant::Int32(2))],
+        );
+        let desired_snapshot = InstanceSnapshot::from_tree(dom_desired, desired_root);
+
+        let project_patch =
+            compute_patch_set(desired_snapshot, &tree_base, tree_base.get_root_id());
+
+        let resolved = reconcile_three_way(
+            project_patch,
+            &tree_base,
+            &tree_old,
+            MergeStrategy::Preserve,
+            false,
+        )
+        .expect("the file never touched Foo, so there's nothing to conflict with");
+
+        let update = resolved
+            .updated_instances
+            .iter()
+            .find(|update| update.id == part)
+            .expect("the one-sided project change should carry over");
+        assert_eq!(update.changed_properties.len(), 1);
+    }
+
+    #[test]
+    fn reconcile_three_way_bails_on_real_conflict_by_default() {
+        let mut dom_base = WeakDom::new(InstanceBuilder::new("DataModel"));
+        let base_root = dom_base.root_ref();
+        child(
+            &mut dom_base,
+            base_root,
+            "Part",
+            "Part",
+            &[("Foo", Variant::Int32(1))],
+        );
+        let tree_base = tree_from(dom_base);
+
+        let mut dom_old = WeakDom::new(InstanceBuilder::new("DataModel"));
+        let old_root = dom_old.root_ref();
+        child(
+            &mut dom_old,
+            old_root,
+            "Part",
+            "Part",
+            &[("Foo", Variant::Int32(9))],
+        );
+        let tree_old = tree_from(dom_old);
+
+        let mut dom_desired = WeakDom::new(InstanceBuilder::new("DataModel"));
+        let desired_root = dom_desired.root_ref();
+        child(
+            &mut dom_desired,
+            desired_root,
+            "Part",
+            "Part",
+            &[("Foo", Variant::Int32(2))],
+        );
+        let desired_snapshot = InstanceSnapshot::from_tree(dom_desired, desired_root);
+
+        let project_patch =
+            compute_patch_set(desired_snapshot, &tree_base, tree_base.get_root_id());
+
+        let result = reconcile_three_way(
+            project_patch,
+            &tree_base,
+            &tree_old,
+            MergeStrategy::Preserve,
+            false,
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn reconcile_three_way_drops_add_with_untranslatable_parent_under_overwrite() {
+        let mut dom_base = WeakDom::new(InstanceBuilder::new("DataModel"));
+        let base_root = dom_base.root_ref();
+        let container = child(&mut dom_base, base_root, "Container", "Folder", &[]);
+        let tree_base = tree_from(dom_base);
+
+        // The file removed `Container` since `base`, so there's nowhere
+        // sound to parent the project's new child in `tree_old`.
+        let dom_old = WeakDom::new(InstanceBuilder::new("DataModel"));
+        let tree_old = tree_from(dom_old);
+
+        let mut dom_desired = WeakDom::new(InstanceBuilder::new("DataModel"));
+        let desired_root = dom_desired.root_ref();
+        let desired_container = child(&mut dom_desired, desired_root, "Container", "Folder", &[]);
+        child(&mut dom_desired, desired_container, "NewPart", "Part", &[]);
+        let desired_snapshot = InstanceSnapshot::from_tree(dom_desired, desired_root);
+
+        let project_patch =
+            compute_patch_set(desired_snapshot, &tree_base, tree_base.get_root_id());
+        assert!(project_patch
+            .added_instances
+            .iter()
+            .any(|add| add.parent_id == container));
+
+        let resolved = reconcile_three_way(
+            project_patch,
+            &tree_base,
+            &tree_old,
+            MergeStrategy::Overwrite,
+            false,
+        )
+        .expect("overwrite should drop the dangling add instead of bailing");
+
+        assert!(resolved.added_instances.is_empty());
+    }
+
+    #[test]
+    fn reconcile_three_way_bails_on_dangling_parent_add_under_preserve() {
+        let mut dom_base = WeakDom::new(InstanceBuilder::new("DataModel"));
+        let base_root = dom_base.root_ref();
+        child(&mut dom_base, base_root, "Container", "Folder", &[]);
+        let tree_base = tree_from(dom_base);
+
+        let dom_old = WeakDom::new(InstanceBuilder::new("DataModel"));
+        let tree_old = tree_from(dom_old);
+
+        let mut dom_desired = WeakDom::new(InstanceBuilder::new("DataModel"));
+        let desired_root = dom_desired.root_ref();
+        let desired_container = child(&mut dom_desired, desired_root, "Container", "Folder", &[]);
+        child(&mut dom_desired, desired_container, "NewPart", "Part", &[]);
+        let desired_snapshot = InstanceSnapshot::from_tree(dom_desired, desired_root);
+
+        let project_patch =
+            compute_patch_set(desired_snapshot, &tree_base, tree_base.get_root_id());
+
+        let result = reconcile_three_way(
+            project_patch,
+            &tree_base,
+            &tree_old,
+            MergeStrategy::Preserve,
+            false,
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn process_model_dom_flattens_a_single_top_level_instance() {
+        let mut wrapper = WeakDom::new(InstanceBuilder::new("Folder"));
+        let wrapper_root = wrapper.root_ref();
+        child(
+            &mut wrapper,
+            wrapper_root,
+            "Model",
+            "Model",
+            &[("Foo", Variant::Int32(1))],
+        );
+
+        let (result, is_single_root) = process_model_dom(wrapper).unwrap();
+
+        assert!(is_single_root);
+        assert_eq!(result.root().class, "Model");
+        assert_eq!(result.root().properties.len(), 1);
+    }
+
+    #[test]
+    fn process_model_dom_passes_through_multiple_top_level_instances() {
+        let mut wrapper = WeakDom::new(InstanceBuilder::new("Folder"));
+        let wrapper_root = wrapper.root_ref();
+        child(&mut wrapper, wrapper_root, "A", "Part", &[]);
+        child(&mut wrapper, wrapper_root, "B", "Part", &[]);
+
+        let (result, is_single_root) = process_model_dom(wrapper).unwrap();
+
+        assert!(!is_single_root);
+        assert_eq!(result.root().children().len(), 2);
+    }
+
+    #[test]
+    fn rbxm_round_trip_preserves_multiple_top_level_instances() {
+        let mut dom = WeakDom::new(InstanceBuilder::new("DataModel"));
+        let root = dom.root_ref();
+        child(&mut dom, root, "A", "Part", &[]);
+        child(&mut dom, root, "B", "Part", &[]);
+        let tree = tree_from(dom);
+
+        let path = std::env::temp_dir().join(format!(
+            "rojo_sync_test_{}_rbxm_round_trip.rbxm",
+            std::process::id()
+        ));
+        write_tree_to_file(&tree, &path, FileKind::Rbxm, false).unwrap();
+
+        let (read_back, is_single_root) = read_dom(&path, FileKind::Rbxm).unwrap();
+        let _ = fs_err::remove_file(&path);
+
+        assert!(!is_single_root);
+        assert_eq!(read_back.root().children().len(), 2);
+    }
+
+    #[test]
+    fn format_patch_summary_reports_no_changes_when_the_patch_set_is_empty() {
+        let tree = tree_from(WeakDom::new(InstanceBuilder::new("DataModel")));
+
+        assert_eq!(
+            format_patch_summary(&PatchSet::default(), &tree),
+            "No changes.\n"
+        );
+    }
+
+    #[test]
+    fn format_patch_summary_lists_added_removed_and_updated_instances() {
+        let mut dom_old = WeakDom::new(InstanceBuilder::new("DataModel"));
+        let old_root = dom_old.root_ref();
+        child(
+            &mut dom_old,
+            old_root,
+            "Part",
+            "Part",
+            &[("Foo", Variant::Int32(1))],
+        );
+        child(&mut dom_old, old_root, "Leftover", "Part", &[]);
+        let tree_old = tree_from(dom_old);
+
+        let mut dom_desired = WeakDom::new(InstanceBuilder::new("DataModel"));
+        let desired_root = dom_desired.root_ref();
+        child(
+            &mut dom_desired,
+            desired_root,
+            "Part",
+            "Part",
+            &[("Foo", Variant::Int32(2))],
+        );
+        child(&mut dom_desired, desired_root, "NewPart", "Part", &[]);
+        let desired_snapshot = InstanceSnapshot::from_tree(dom_desired, desired_root);
+
+        let patch_set = compute_patch_set(desired_snapshot, &tree_old, tree_old.get_root_id());
+
+        let summary = format_patch_summary(&patch_set, &tree_old);
+
+        assert!(summary.contains("+ NewPart (Part)"));
+        assert!(summary.contains("- Leftover"));
+        assert!(summary.contains("~ Part"));
+    }
+}
@@ -188,13 +188,24 @@ pub fn sourcemap(
 }
 
 #[napi]
-pub fn sync(project: String, input: String, output: String) -> Result<(), napi::Error> {
-    use crate::cli::SyncCommand;
+pub fn sync(
+    project: String,
+    input: String,
+    output: String,
+    list: bool,
+    dry_run: bool,
+) -> Result<(), napi::Error> {
+    use crate::cli::{MergeStrategy, SyncCommand};
 
     SyncCommand {
         project: project.into(),
         input: input.into(),
         output: output.into(),
+        strategy: MergeStrategy::Preserve,
+        base: None,
+        allow_conflicts: false,
+        dry_run,
+        list,
     }
     .run()
     .map_err(|e| napi::Error::from_reason(e.to_string()))